@@ -3,15 +3,22 @@
 
 //! Support for partitioning test runs across several machines.
 //!
-//! At the moment this only supports simple hash-based and count-based sharding. In the future it
-//! could potentially be made smarter: e.g. using data to pick different sets of binaries and tests
-//! to run, with an aim to minimize total build and test times.
+//! Besides simple hash-based and count-based sharding, this also supports [`HashBinary`](
+//! PartitionerBuilder::HashBinary) partitioning, which hashes a test's binary id instead of its
+//! name so that every test in a binary stays on one shard; [`Timed`](PartitionerBuilder::Timed)
+//! partitioning, which uses recorded per-test durations to greedily balance total wall-clock time
+//! across shards instead of just test counts; and [`Ring`](PartitionerBuilder::Ring)
+//! partitioning, which uses consistent hashing to limit how much reshuffling happens when
+//! `total_shards` changes.
 
 use crate::errors::PartitionerBuilderParseError;
 use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
     fmt,
-    hash::{Hash, Hasher},
+    hash::Hasher,
     str::FromStr,
+    time::Duration,
 };
 use twox_hash::XxHash64;
 
@@ -38,19 +45,76 @@ pub enum PartitionerBuilder {
 
         /// The total number of shards.
         total_shards: u64,
+
+        /// A seed mixed into the hash of each test name.
+        ///
+        /// Changing the seed reshuffles which shard each test lands on without changing the
+        /// shard count, which is useful for rebalancing a retry away from a shard that drew a
+        /// cluster of slow tests. Defaults to 0 when no `:seed=` is given.
+        seed: u64,
+    },
+
+    /// Partition based on hashing the test binary's id, so every test within a given binary lands
+    /// on the same shard. This avoids loading and dynamically linking the same (potentially
+    /// large) test binary on every shard, at the cost of only spreading load at binary
+    /// granularity rather than per-test.
+    HashBinary {
+        /// The shard this is in, counting up from 1.
+        shard: u64,
+
+        /// The total number of shards.
+        total_shards: u64,
+
+        /// A seed mixed into the hash of each binary id, for the same reason `Hash`'s seed is
+        /// useful: reshuffling which shard a binary lands on without changing the shard count.
+        seed: u64,
+    },
+
+    /// Partition using recorded per-test durations, greedily balancing total wall-clock time
+    /// across shards rather than just the number of tests.
+    Timed {
+        /// The shard this is in, counting up from 1.
+        shard: u64,
+
+        /// The total number of shards.
+        total_shards: u64,
+
+        /// Recorded durations for tests, typically parsed from a prior JUnit or libtest run.
+        ///
+        /// A value of `None` means the test is known (e.g. it's new since the history was last
+        /// collected, so no duration could be measured) but has no recorded duration, and
+        /// `default_duration` is substituted for it; this is kept distinct from `Some(Duration::
+        /// ZERO)`, which is a genuinely-measured instantaneous test and is used as-is. Only tests
+        /// present here are covered by the greedy LPT assignment; a test missing from `durations`
+        /// entirely instead falls back to the unbiased hash mapping, with a warning logged, so
+        /// it's never silently dropped from every shard.
+        durations: BTreeMap<String, Option<Duration>>,
+
+        /// The duration substituted for any test whose recorded duration in `durations` is
+        /// `None` (i.e. unmeasured).
+        default_duration: Duration,
+    },
+
+    /// Partition using consistent hashing, so that growing or shrinking `total_shards` only
+    /// reshuffles roughly `1/total_shards` of tests instead of nearly all of them.
+    Ring {
+        /// The shard this is in, counting up from 1.
+        shard: u64,
+
+        /// The total number of shards.
+        total_shards: u64,
     },
 }
 
 /// Represents an individual partitioner, typically scoped to a test binary.
 pub trait Partitioner: fmt::Debug {
-    /// Returns true if the given test name matches the partition.
-    fn test_matches(&self, test_name: &str, index: usize) -> bool;
+    /// Returns true if the given test matches the partition.
+    fn test_matches(&self, binary_id: &str, test_name: &str, index: usize) -> bool;
 }
 
 impl PartitionerBuilder {
     /// Creates a new `Partitioner` from this `PartitionerBuilder`.
     pub fn build(&self) -> Box<dyn Partitioner> {
-        // Note we don't use test_binary at the moment but might in the future.
         match self {
             PartitionerBuilder::Count {
                 shard,
@@ -59,7 +123,28 @@ impl PartitionerBuilder {
             PartitionerBuilder::Hash {
                 shard,
                 total_shards,
-            } => Box::new(HashPartitioner::new(*shard, *total_shards)),
+                seed,
+            } => Box::new(HashPartitioner::new(*shard, *total_shards, *seed)),
+            PartitionerBuilder::HashBinary {
+                shard,
+                total_shards,
+                seed,
+            } => Box::new(HashBinaryPartitioner::new(*shard, *total_shards, *seed)),
+            PartitionerBuilder::Timed {
+                shard,
+                total_shards,
+                durations,
+                default_duration,
+            } => Box::new(TimedPartitioner::new(
+                *shard,
+                *total_shards,
+                durations,
+                *default_duration,
+            )),
+            PartitionerBuilder::Ring {
+                shard,
+                total_shards,
+            } => Box::new(RingPartitioner::new(*shard, *total_shards)),
         }
     }
 }
@@ -68,13 +153,25 @@ impl FromStr for PartitionerBuilder {
     type Err = PartitionerBuilderParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Parse the string: it looks like "hash:<shard>/<total_shards>".
+        // Parse the string: it looks like "hash:<shard>/<total_shards>", optionally followed by
+        // ":seed=<u64-or-hex>".
         if let Some(input) = s.strip_prefix("hash:") {
-            let (shard, total_shards) = parse_shards(input, "hash:M/N")?;
+            let (shard, total_shards, seed) =
+                parse_hash_spec(input, "hash:M/N[:seed=<u64-or-hex>]")?;
 
             Ok(PartitionerBuilder::Hash {
                 shard,
                 total_shards,
+                seed,
+            })
+        } else if let Some(input) = s.strip_prefix("hash-binary:") {
+            let (shard, total_shards, seed) =
+                parse_hash_spec(input, "hash-binary:M/N[:seed=<u64-or-hex>]")?;
+
+            Ok(PartitionerBuilder::HashBinary {
+                shard,
+                total_shards,
+                seed,
             })
         } else if let Some(input) = s.strip_prefix("count:") {
             let (shard, total_shards) = parse_shards(input, "count:M/N")?;
@@ -83,11 +180,18 @@ impl FromStr for PartitionerBuilder {
                 shard,
                 total_shards,
             })
+        } else if let Some(input) = s.strip_prefix("ring:") {
+            let (shard, total_shards) = parse_shards(input, "ring:M/N")?;
+
+            Ok(PartitionerBuilder::Ring {
+                shard,
+                total_shards,
+            })
         } else {
             Err(PartitionerBuilderParseError::new(
                 None,
                 format!(
-                    "partition input '{}' must begin with \"hash:\" or \"count:\"",
+                    "partition input '{}' must begin with \"hash:\", \"hash-binary:\", \"count:\" or \"ring:\"",
                     s
                 ),
             ))
@@ -95,6 +199,20 @@ impl FromStr for PartitionerBuilder {
     }
 }
 
+/// Parses a `<shard>/<total_shards>`, optionally followed by `:seed=<u64-or-hex>`, spec shared by
+/// the `hash:` and `hash-binary:` prefixes.
+fn parse_hash_spec(
+    input: &str,
+    expected_format: &'static str,
+) -> Result<(u64, u64, u64), PartitionerBuilderParseError> {
+    let (shards_input, seed) = match input.split_once(":seed=") {
+        Some((shards_input, seed_str)) => (shards_input, parse_seed(seed_str, expected_format)?),
+        None => (input, 0),
+    };
+    let (shard, total_shards) = parse_shards(shards_input, expected_format)?;
+    Ok((shard, total_shards, seed))
+}
+
 fn parse_shards(
     input: &str,
     expected_format: &'static str,
@@ -141,6 +259,26 @@ fn parse_shards(
     Ok((shard, total_shards))
 }
 
+fn parse_seed(
+    input: &str,
+    expected_format: &'static str,
+) -> Result<u64, PartitionerBuilderParseError> {
+    let seed = match input.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => input.parse(),
+    };
+
+    seed.map_err(|err| {
+        PartitionerBuilderParseError::new(
+            Some(expected_format),
+            format!(
+                "failed to parse seed '{}' as a u64 or hex literal: {}",
+                input, err
+            ),
+        )
+    })
+}
+
 #[derive(Clone, Debug)]
 struct CountPartitioner {
     shard_minus_one: u64,
@@ -158,7 +296,7 @@ impl CountPartitioner {
 }
 
 impl Partitioner for CountPartitioner {
-    fn test_matches(&self, _test_name: &str, index: usize) -> bool {
+    fn test_matches(&self, _binary_id: &str, _test_name: &str, index: usize) -> bool {
         (index as u64) % self.total_shards == self.shard_minus_one
     }
 }
@@ -167,23 +305,206 @@ impl Partitioner for CountPartitioner {
 struct HashPartitioner {
     shard_minus_one: u64,
     total_shards: u64,
+    seed: u64,
 }
 
 impl HashPartitioner {
-    fn new(shard: u64, total_shards: u64) -> Self {
+    fn new(shard: u64, total_shards: u64, seed: u64) -> Self {
         let shard_minus_one = shard - 1;
         Self {
             shard_minus_one,
             total_shards,
+            seed,
         }
     }
 }
 
 impl Partitioner for HashPartitioner {
-    fn test_matches(&self, test_name: &str, _index: usize) -> bool {
-        let mut hasher = XxHash64::default();
-        test_name.hash(&mut hasher);
-        hasher.finish() % self.total_shards == self.shard_minus_one
+    fn test_matches(&self, _binary_id: &str, test_name: &str, _index: usize) -> bool {
+        hash_to_shard(
+            hash_seeded_bytes(self.seed, test_name.as_bytes()),
+            self.total_shards,
+        ) == self.shard_minus_one
+    }
+}
+
+/// The canonical partition function for a seed and a name: hashes the seed followed by the raw
+/// UTF-8 bytes of `name`, with no additional framing.
+///
+/// We deliberately avoid going through `name`'s `Hash` impl: `str`'s `Hash` implementation writes
+/// a trailing length delimiter in addition to the bytes, which would make the resulting shard
+/// assignment an implementation detail of `std` rather than a stable, documented function of
+/// `name` alone. Hashing the bytes directly keeps assignments reproducible across toolchain
+/// versions and machines.
+fn hash_seeded_bytes(seed: u64, bytes: &[u8]) -> u64 {
+    let mut hasher = XxHash64::default();
+    // `Hasher::write_u64` serializes via `to_ne_bytes()`, which would make the seed's contribution
+    // (and so the resulting shard assignment) depend on the host's endianness. Write a fixed,
+    // explicit byte order instead, matching `replica.to_le_bytes()` in `ring_token`.
+    hasher.write(&seed.to_le_bytes());
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[derive(Clone, Debug)]
+struct HashBinaryPartitioner {
+    shard_minus_one: u64,
+    total_shards: u64,
+    seed: u64,
+}
+
+impl HashBinaryPartitioner {
+    fn new(shard: u64, total_shards: u64, seed: u64) -> Self {
+        let shard_minus_one = shard - 1;
+        Self {
+            shard_minus_one,
+            total_shards,
+            seed,
+        }
+    }
+}
+
+impl Partitioner for HashBinaryPartitioner {
+    fn test_matches(&self, binary_id: &str, _test_name: &str, _index: usize) -> bool {
+        hash_to_shard(
+            hash_seeded_bytes(self.seed, binary_id.as_bytes()),
+            self.total_shards,
+        ) == self.shard_minus_one
+    }
+}
+
+/// Maps a 64-bit hash into `0..total_shards` using a multiply-shift rather than a modulo, so that
+/// every shard gets a contiguous, near-equal-sized slice of the hash space instead of modulo's
+/// bias toward lower-numbered shards when `total_shards` doesn't evenly divide 2^64.
+fn hash_to_shard(hash: u64, total_shards: u64) -> u64 {
+    ((total_shards as u128 * hash as u128) / (u64::MAX as u128 + 1)) as u64
+}
+
+#[derive(Clone, Debug)]
+struct TimedPartitioner {
+    shard_minus_one: u64,
+    total_shards: u64,
+    // The set of test names assigned to this shard by the greedy LPT pass in `new`.
+    assigned: BTreeSet<String>,
+    // Every test name present in `durations` (measured or not), so `test_matches` can tell
+    // "assigned to another shard" apart from "never seen" and give the latter a fallback
+    // assignment.
+    known: BTreeSet<String>,
+}
+
+impl TimedPartitioner {
+    fn new(
+        shard: u64,
+        total_shards: u64,
+        durations: &BTreeMap<String, Option<Duration>>,
+        default_duration: Duration,
+    ) -> Self {
+        let shard_minus_one = (shard - 1) as usize;
+
+        // Longest-processing-time-first: sort tests by descending duration, then greedily assign
+        // each one to whichever shard currently has the least accumulated load. Ties in duration
+        // are broken by name so the assignment is deterministic.
+        let mut tests: Vec<(&str, Duration)> = durations
+            .iter()
+            .map(|(name, duration)| (name.as_str(), duration.unwrap_or(default_duration)))
+            .collect();
+        tests.sort_by(|(name_a, duration_a), (name_b, duration_b)| {
+            duration_b.cmp(duration_a).then_with(|| name_a.cmp(name_b))
+        });
+
+        let mut loads: BinaryHeap<Reverse<(Duration, usize)>> = (0..total_shards as usize)
+            .map(|shard_index| Reverse((Duration::ZERO, shard_index)))
+            .collect();
+
+        let mut assigned = BTreeSet::new();
+        for (name, duration) in tests {
+            let Reverse((load, shard_index)) =
+                loads.pop().expect("loads always has total_shards entries");
+            if shard_index == shard_minus_one {
+                assigned.insert(name.to_owned());
+            }
+            loads.push(Reverse((load + duration, shard_index)));
+        }
+
+        Self {
+            shard_minus_one: shard_minus_one as u64,
+            total_shards,
+            assigned,
+            known: durations.keys().cloned().collect(),
+        }
+    }
+}
+
+impl Partitioner for TimedPartitioner {
+    fn test_matches(&self, _binary_id: &str, test_name: &str, _index: usize) -> bool {
+        if self.known.contains(test_name) {
+            return self.assigned.contains(test_name);
+        }
+
+        // This test is missing from `durations` entirely (e.g. it's new since the history was
+        // last collected), so it wasn't covered by the LPT pass in `new`. Rather than silently
+        // matching no shard at all, fall back to the unbiased hash mapping so it still lands on
+        // exactly one shard.
+        tracing::warn!(
+            "test '{}' is missing from the recorded durations for timed partitioning; \
+             falling back to hash-based shard assignment",
+            test_name,
+        );
+        hash_to_shard(
+            hash_seeded_bytes(0, test_name.as_bytes()),
+            self.total_shards,
+        ) == self.shard_minus_one
+    }
+}
+
+/// The number of virtual-node tokens generated per shard on the consistent-hashing ring.
+///
+/// More replicas spread each shard's tokens more evenly around the ring, at the cost of a larger
+/// (but still tiny) token array to binary search.
+const RING_REPLICAS_PER_SHARD: u64 = 128;
+
+#[derive(Clone, Debug)]
+struct RingPartitioner {
+    shard_minus_one: u64,
+    // Virtual-node tokens sorted ascending, each tagged with the shard (0-indexed) that owns it.
+    ring: Vec<(u64, u64)>,
+}
+
+impl RingPartitioner {
+    fn new(shard: u64, total_shards: u64) -> Self {
+        let mut ring: Vec<(u64, u64)> = (0..total_shards)
+            .flat_map(|shard_index| {
+                (0..RING_REPLICAS_PER_SHARD)
+                    .map(move |replica| (ring_token(shard_index, replica), shard_index))
+            })
+            .collect();
+        ring.sort_unstable_by_key(|(token, _)| *token);
+
+        Self {
+            shard_minus_one: shard - 1,
+            ring,
+        }
+    }
+}
+
+fn ring_token(shard_index: u64, replica: u64) -> u64 {
+    // Same canonical byte-level hashing as `hash_seeded_bytes`: keying on shard_index and hashing
+    // replica's raw bytes keeps virtual-node tokens independent of std's `Hash` impl, so the ring
+    // is reproducible across toolchain versions and machines.
+    hash_seeded_bytes(shard_index, &replica.to_le_bytes())
+}
+
+impl Partitioner for RingPartitioner {
+    fn test_matches(&self, _binary_id: &str, test_name: &str, _index: usize) -> bool {
+        let token = hash_seeded_bytes(0, test_name.as_bytes());
+
+        // Walk clockwise to the first virtual node whose token is >= ours, wrapping back to the
+        // start of the ring if we've passed every token.
+        let owner_index = self
+            .ring
+            .partition_point(|(node_token, _)| *node_token < token)
+            % self.ring.len();
+        self.ring[owner_index].1 == self.shard_minus_one
     }
 }
 
@@ -199,6 +520,7 @@ mod tests {
                 PartitionerBuilder::Hash {
                     shard: 1,
                     total_shards: 2,
+                    seed: 0,
                 },
             ),
             (
@@ -206,6 +528,7 @@ mod tests {
                 PartitionerBuilder::Hash {
                     shard: 1,
                     total_shards: 1,
+                    seed: 0,
                 },
             ),
             (
@@ -213,6 +536,23 @@ mod tests {
                 PartitionerBuilder::Hash {
                     shard: 99,
                     total_shards: 200,
+                    seed: 0,
+                },
+            ),
+            (
+                "hash:1/2:seed=42",
+                PartitionerBuilder::Hash {
+                    shard: 1,
+                    total_shards: 2,
+                    seed: 42,
+                },
+            ),
+            (
+                "hash:1/2:seed=0xdeadbeef",
+                PartitionerBuilder::Hash {
+                    shard: 1,
+                    total_shards: 2,
+                    seed: 0xdeadbeef,
                 },
             ),
         ];
@@ -228,6 +568,8 @@ mod tests {
             "hash:m/2",
             "hash:1/n",
             "hash:1/2/3",
+            "hash:1/2:seed=",
+            "hash:1/2:seed=notaseed",
         ];
 
         for (input, output) in successes {
@@ -247,4 +589,276 @@ mod tests {
                 .expect_err(&format!("expected input '{}' to fail", input));
         }
     }
+
+    #[test]
+    fn timed_partitioner_balances_load() {
+        let durations: BTreeMap<String, Option<Duration>> = vec![
+            ("slow".to_owned(), Some(Duration::from_secs(10))),
+            ("medium-1".to_owned(), Some(Duration::from_secs(5))),
+            ("medium-2".to_owned(), Some(Duration::from_secs(5))),
+            ("fast-1".to_owned(), Some(Duration::from_secs(1))),
+            ("fast-2".to_owned(), Some(Duration::from_secs(1))),
+        ]
+        .into_iter()
+        .collect();
+
+        // 2 shards: the greedy LPT pass should put "slow" on one shard and both "medium" tests
+        // together on the other (10s vs 5s+5s = 10s); the two 1s tests then go one to each
+        // shard, keeping the shards balanced at 11s each.
+        let builders = (1..=2).map(|shard| PartitionerBuilder::Timed {
+            shard,
+            total_shards: 2,
+            durations: durations.clone(),
+            default_duration: Duration::from_secs(1),
+        });
+
+        let mut shard_of = std::collections::HashMap::new();
+        for (shard, builder) in (1..=2u64).zip(builders) {
+            let partitioner = builder.build();
+            for name in durations.keys() {
+                if partitioner.test_matches("test_bin", name, 0) {
+                    shard_of.insert(name.clone(), shard);
+                }
+            }
+        }
+
+        assert_eq!(
+            shard_of.len(),
+            durations.len(),
+            "every test is assigned to exactly one shard"
+        );
+        assert_eq!(
+            shard_of["medium-1"], shard_of["medium-2"],
+            "both medium tests should share a shard"
+        );
+        assert_ne!(
+            shard_of["slow"], shard_of["medium-1"],
+            "the slow test should not share a shard with the medium tests"
+        );
+    }
+
+    #[test]
+    fn timed_partitioner_distinguishes_zero_from_unmeasured() {
+        // "instant" is genuinely measured at 0ns and should be treated as the lightest possible
+        // test; if it were instead conflated with "unmeasured" (recorded as `None`, weighted as
+        // the much larger `default_duration`), the LPT pass would isolate it on its own shard
+        // rather than grouping it with the other light tests.
+        let durations: BTreeMap<String, Option<Duration>> = vec![
+            ("heavy".to_owned(), Some(Duration::from_secs(10))),
+            ("light-1".to_owned(), Some(Duration::from_secs(1))),
+            ("light-2".to_owned(), Some(Duration::from_secs(1))),
+            ("instant".to_owned(), Some(Duration::ZERO)),
+        ]
+        .into_iter()
+        .collect();
+        let total_shards = 2;
+        // Deliberately larger than "heavy"'s duration, so a buggy substitution of
+        // `default_duration` for "instant" would sort it before "heavy" and isolate it alone.
+        let default_duration = Duration::from_secs(100);
+
+        let shard_of = |name: &str| -> u64 {
+            (1..=total_shards)
+                .find(|&shard| {
+                    PartitionerBuilder::Timed {
+                        shard,
+                        total_shards,
+                        durations: durations.clone(),
+                        default_duration,
+                    }
+                    .build()
+                    .test_matches("test_bin", name, 0)
+                })
+                .expect("every known test should match exactly one shard")
+        };
+
+        assert_eq!(
+            shard_of("instant"),
+            shard_of("light-1"),
+            "a genuinely-instantaneous test should group with the other light tests, not be \
+             weighted as if unmeasured"
+        );
+        assert_ne!(
+            shard_of("instant"),
+            shard_of("heavy"),
+            "the heavy test should be isolated on its own shard"
+        );
+    }
+
+    #[test]
+    fn timed_partitioner_falls_back_for_unknown_tests() {
+        let durations: BTreeMap<String, Option<Duration>> =
+            vec![("known".to_owned(), Some(Duration::from_secs(1)))]
+                .into_iter()
+                .collect();
+        let total_shards = 4;
+
+        // A test missing from `durations` should still match exactly one shard, via the
+        // hash-based fallback, rather than silently matching none.
+        let matching_shards: Vec<u64> = (1..=total_shards)
+            .filter(|&shard| {
+                PartitionerBuilder::Timed {
+                    shard,
+                    total_shards,
+                    durations: durations.clone(),
+                    default_duration: Duration::from_secs(1),
+                }
+                .build()
+                .test_matches("test_bin", "brand_new_test", 0)
+            })
+            .collect();
+
+        assert_eq!(
+            matching_shards.len(),
+            1,
+            "an unknown test should fall back to exactly one shard, matched {:?}",
+            matching_shards
+        );
+    }
+
+    #[test]
+    fn ring_partitioner_limits_reshuffling() {
+        let test_names: Vec<String> = (0..1000).map(|i| format!("test_{}", i)).collect();
+
+        let assign = |total_shards: u64| -> Vec<u64> {
+            let partitioners: Vec<_> = (1..=total_shards)
+                .map(|shard| {
+                    PartitionerBuilder::Ring {
+                        shard,
+                        total_shards,
+                    }
+                    .build()
+                })
+                .collect();
+
+            test_names
+                .iter()
+                .map(|name| {
+                    partitioners
+                        .iter()
+                        .position(|p| p.test_matches("test_bin", name, 0))
+                        .expect("every test should match exactly one shard")
+                        as u64
+                })
+                .collect()
+        };
+
+        let before = assign(4);
+        let after = assign(5);
+
+        let moved = before.iter().zip(&after).filter(|(a, b)| a != b).count();
+
+        // Growing from 4 to 5 shards should move roughly 1/5 of tests, nowhere near the ~100% a
+        // plain hash-mod remap would cause.
+        assert!(
+            moved < test_names.len() / 2,
+            "expected well under half of tests to move, but {} of {} did",
+            moved,
+            test_names.len()
+        );
+    }
+
+    #[test]
+    fn hash_seeded_bytes_uses_fixed_endianness() {
+        // Regression test: the seed must be mixed in via an explicit, fixed byte order rather
+        // than `Hasher::write_u64`'s native-endian default, so a non-zero seed's shard assignment
+        // doesn't silently depend on the host's endianness.
+        let seed = 0x0102030405060708u64;
+        let bytes = b"some-test-name";
+
+        let mut expected_hasher = XxHash64::default();
+        expected_hasher.write(&seed.to_le_bytes());
+        expected_hasher.write(bytes);
+
+        assert_eq!(
+            hash_seeded_bytes(seed, bytes),
+            expected_hasher.finish(),
+            "hash_seeded_bytes should mix in the seed via to_le_bytes, not write_u64"
+        );
+    }
+
+    #[test]
+    fn hash_partitioner_pins_shard_assignments() {
+        // Regression test: these (name, shard) pairs are a documented, stable function of the
+        // name alone (for seed 0), computed by hashing the raw UTF-8 bytes directly. If this test
+        // ever needs to change, every user relying on reproducible shard assignments across
+        // toolchain versions and machines is affected.
+        let total_shards = 4;
+        let expected = [
+            ("test_0", 1u64),
+            ("test_2", 2u64),
+            ("test_3", 3u64),
+            ("test_13", 4u64),
+        ];
+
+        for (name, expected_shard) in expected {
+            for shard in 1..=total_shards {
+                let partitioner = PartitionerBuilder::Hash {
+                    shard,
+                    total_shards,
+                    seed: 0,
+                }
+                .build();
+                assert_eq!(
+                    partitioner.test_matches("test_bin", name, 0),
+                    shard == expected_shard,
+                    "name '{}' expected on shard {} of {}, checked shard {}",
+                    name,
+                    expected_shard,
+                    total_shards,
+                    shard,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hash_binary_partitioner_colocates_tests_in_a_binary() {
+        let total_shards = 4;
+        let binary_ids = ["binary-a", "binary-b", "binary-c", "binary-d"];
+
+        let shard_of_binary = |binary_id: &str| -> u64 {
+            (1..=total_shards)
+                .find(|&shard| {
+                    PartitionerBuilder::HashBinary {
+                        shard,
+                        total_shards,
+                        seed: 0,
+                    }
+                    .build()
+                    .test_matches(binary_id, "irrelevant_test_name", 0)
+                })
+                .expect("every binary id should match exactly one shard")
+        };
+
+        for binary_id in binary_ids {
+            let expected_shard = shard_of_binary(binary_id);
+
+            // Every test name in the same binary lands on the same shard as the binary itself,
+            // regardless of the test name.
+            for test_name in ["test_one", "test_two", "some::other::test"] {
+                let partitioner = PartitionerBuilder::HashBinary {
+                    shard: expected_shard,
+                    total_shards,
+                    seed: 0,
+                }
+                .build();
+                assert!(
+                    partitioner.test_matches(binary_id, test_name, 0),
+                    "test '{}' in binary '{}' should match binary's shard {}",
+                    test_name,
+                    binary_id,
+                    expected_shard,
+                );
+            }
+        }
+
+        // Distinct binary ids should spread out across shards rather than all landing together.
+        let shards: std::collections::BTreeSet<u64> =
+            binary_ids.iter().map(|id| shard_of_binary(id)).collect();
+        assert!(
+            shards.len() > 1,
+            "expected binary ids to spread across more than one shard, got {:?}",
+            shards
+        );
+    }
 }